@@ -29,16 +29,21 @@
 //! |------|--------|-----|-----|
 //! | 50.6969 ms | 1.14666 ms | 644.39236 μs | 278.15658236 ms |
 //!
+//! Výše uvedená tabulka pochází ještě z chronologicky couvající DPLL verze solveru;
+//! od zavedení CDCL a sledovaných literálů (viz [`solve`]) by se čísla na stejné sadě
+//! měla výrazně zlepšit, ale tabulku jsem zatím přeměřit nestihl.
+//!
 //! # Popis implementace
 //!
-//! Máme [strukturu s počítadli zachicující statistiky pro výpis](`State`),
-//! a [strukturu zachicující uzly rozhodovacího stromu](`Formula`) s [přiřazeními](`Assignment`)
-//! a ještě nezplněnými [klauzelemi](`Clause`) v daném uzlu.
+//! Máme [strukturu s počítadli zachicující statistiky pro výpis, couvací zásobník
+//! (`trail`) a pole hodnot indexované proměnnou](`State`), a [strukturu zachicující
+//! klauzule řešeného problému se sledovanými literály](`Formula`).
 //!
 //! Průběh výpočtu je popsán ve funkci [`solve`].
 //!
 
 use anyhow::{Context, Result};
+use std::collections::VecDeque;
 use std::{env, fs};
 
 /// stejně jako v [DIMACS formátu](https://web.archive.org/web/20190325181937/https://www.satcompetition.org/2009/format-benchmarks2009.html)
@@ -56,99 +61,889 @@ pub type Assignment = Literal;
 ///
 type Clause = Vec<Literal>;
 
-/// Reprezentuje stav prohledávaného podstromu možných [přiřazení](`Assignment`).
-/// A to seznamem [přiřazení](`Assignment`), kterými jsme se od původního problému
-/// dostali k tomuto stavu, a seznamem [klauzilí](`Clause`) pouze s [literáli](`Literal`),
-/// které jsou stále nerozhodnuty.
+/// Index proměnné, ke kterému se přistupuje do [`State::values`].
+///
+fn variable_of(literal: Literal) -> usize {
+    literal.unsigned_abs()
+}
+
+/// Původ [klauzule](`Clause`): buď přímo ze vstupního souboru (redukce databáze naučených
+/// klauzulí ji nikdy nemaže, [vivifikace](`Formula::vivify_clauses`) ano), nebo naučená
+/// analýzou konfliktu a označkovaná svým LBD ("glue") — počtem rozdílných rozhodovacích úrovní
+/// mezi jejími literály v momentě naučení, viz [`Formula::reduce_learned_clauses`].
+///
+#[derive(Debug, Clone, Copy)]
+enum ClauseOrigin {
+    Original,
+    Learned { lbd: usize },
+}
+
+/// Reprezentuje klauzule řešeného problému. [Klauzule](`Clause`) se fyzicky nezkracují
+/// ani neodstraňují podle aktuálního (částečného) přiřazení — místo toho si pro každou
+/// klauzuli evidujeme indexy dvou "sledovaných" literálů (`watched`) a pro každý literál
+/// seznam klauzulí, které ho právě sledují (`watches`). Díky tomu propagace navštíví jen
+/// klauzule dotčené posledním přiřazením, místo aby procházela celou formuli.
+///
+/// Kromě původních klauzulí ze vstupního souboru sem [`solve`] postupně přidává i klauzule
+/// naučené analýzou konfliktu, viz [`Formula::add_learned_clause`]. Indexy klauzulí jsou stabilní po
+/// celou dobu života formule — smazané klauzule (viz [`Formula::reduce_learned_clauses`]) si
+/// jen ponechají svůj index s příznakem `deleted`, aby `trail` i naučené klauzule mohly dál
+/// odkazovat na reason klauzule podle indexu.
 ///
 #[derive(Debug, Default, Clone)]
 pub struct Formula {
-    assignments: Vec<Assignment>,
     clauses: Vec<Clause>,
+    /// Pro každou klauzuli indexy jejích dvou sledovaných literálů uvnitř `clauses[i]`.
+    /// U jednoliterálových klauzulí jsou oba indexy stejné.
+    watched: Vec<[usize; 2]>,
+    /// Literál -> indexy klauzulí, které ho právě sledují.
+    watches: hashbrown::HashMap<Literal, Vec<usize>>,
+    /// Původ klauzule na daném indexu, viz [`ClauseOrigin`].
+    origin: Vec<ClauseOrigin>,
+    /// `true`, pokud byla klauzule na daném indexu [smazána](`Formula::reduce_learned_clauses`)
+    /// z databáze naučených klauzulí — propagace a analýza konfliktu takové klauzule ignorují.
+    deleted: Vec<bool>,
+    /// `true`, pokud formule obsahuje (typicky ze vstupního souboru) prázdnou klauzuli —
+    /// tedy je triviálně nesplnitelná bez ohledu na [předpoklady](`Solver::solve_under_assumptions`).
+    /// Prázdná klauzule nemá co sledovat, takže by jinak zůstala pro sledovaná literály
+    /// i analýzu konfliktu neviditelná, viz [`Formula::add_clause_with_origin`].
+    has_empty_clause: bool,
+    nvars: usize,
 }
 
-/// Zachicuje počet prozkoumaných uzlů, a počet použití [unit
-/// propagace](`Formula::unit_propagate`).
-#[derive(Default)]
+/// Faktor, jehož převrácenou hodnotou se po každém konfliktu vynásobí krok přičítaný k [aktivitě
+/// proměnných](`State::activity`) — novější konflikty tak postupně převáží starší, viz
+/// [`State::decay_activity`].
+///
+const ACTIVITY_DECAY: f64 = 0.95;
+
+/// Práh [aktivity proměnných](`State::activity`), po jehož překročení se všechny aktivity
+/// i přičítaný krok přeškálují dolů, aby nepřetekly `f64`.
+///
+const ACTIVITY_RESCALE_THRESHOLD: f64 = 1e100;
+
+/// Počet konfliktů do první [redukce databáze naučených klauzulí](`Formula::reduce_learned_clauses`).
+/// Práh se po každé redukci geometricky zvětší, viz [`Solver::solve_under_assumptions`].
+///
+const INITIAL_REDUCTION_BUDGET: usize = 100;
+
+/// Počet konfliktů do první [vivifikace](`Formula::vivify_clauses`). Práh se po každé
+/// vivifikaci geometricky zvětší, viz [`Solver::solve_under_assumptions`].
+///
+const INITIAL_VIVIFICATION_BUDGET: usize = 200;
+
+/// Počet konfliktů odpovídající jedné jednotce [Lubyho posloupnosti](`luby`) v [`RestartStrategy::Luby`].
+///
+const LUBY_UNIT: usize = 32;
+
+/// Velikost klouzavého okna posledních LBD použitých pro krátkodobý průměr v
+/// [`RestartStrategy::Dynamic`].
+///
+const DYNAMIC_RESTART_WINDOW: usize = 50;
+
+/// Násobek celkového průměru LBD, který musí krátkodobý průměr překročit, aby
+/// [`RestartStrategy::Dynamic`] vyhlásil restart.
+///
+const DYNAMIC_RESTART_LBD_FACTOR: f64 = 1.25;
+
+/// Násobek klouzavého průměru délky `trail`, který couvací zásobník nesmí překročit, jinak
+/// [`RestartStrategy::Dynamic`] restart zablokuje — jsme-li neobvykle hluboko, vyplatí se
+/// ještě chvíli pokračovat, než couvat na úroveň `0`.
+///
+const DYNAMIC_RESTART_TRAIL_BLOCK_FACTOR: f64 = 1.4;
+
+/// Vrátí `i`-tý člen (od `1`) standardní Lubyho posloupnosti reluktantního zdvojování
+/// `1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ...`, používané k rozvrhování restartů
+/// v [`RestartStrategy::Luby`]: `t_i = 2^(k-1)`, pokud `i = 2^k - 1`, jinak
+/// `t_i = t_{i - 2^(k-1) + 1}` pro nejmenší takové `k`.
+///
+fn luby(i: usize) -> usize {
+    let mut k = 1;
+    while (1 << k) - 1 < i {
+        k += 1;
+    }
+
+    if (1 << k) - 1 == i {
+        1 << (k - 1)
+    } else {
+        luby(i - (1 << (k - 1)) + 1)
+    }
+}
+
+/// Strategie rozvrhování restartů — [`Solver::solve_under_assumptions`] po konfliktu couvne
+/// celý `trail` zpět na úroveň `0`, ale naučené klauzule, sledovaná literály i aktivity
+/// proměnných zůstávají, aby se hledání nevracelo úplně od nuly.
+///
+#[derive(Debug, Clone, Copy)]
+pub enum RestartStrategy {
+    /// Restart po `LUBY_UNIT * `[`luby`]`(i)` konfliktech od posledního restartu, kde `i` je
+    /// pořadí aktuálního restartu.
+    Luby,
+    /// Restart, jakmile klouzavý průměr LBD posledních [`DYNAMIC_RESTART_WINDOW`] naučených
+    /// klauzulí překročí [`DYNAMIC_RESTART_LBD_FACTOR`]-násobek celkového průměru — nestane
+    /// se tak, pokud je `trail` neobvykle dlouhý, viz [`DYNAMIC_RESTART_TRAIL_BLOCK_FACTOR`].
+    Dynamic,
+}
+
+/// Jeden záznam na [couvacím zásobníku](`State::trail`): přiřazený [literál](`Literal`),
+/// rozhodovací úroveň, ve které k přiřazení došlo, a u odvozených (nikoliv
+/// rozhodnutých) literálů index [klauzule](`Clause`), která přiřazení vynutila
+/// (tzv. "reason" klauzule).
+///
+#[derive(Debug, Clone, Copy)]
+struct TrailEntry {
+    literal: Literal,
+    level: usize,
+    reason: Option<usize>,
+}
+
+/// Jeden krok [DRAT](https://www.cs.utexas.edu/~marijn/drat-trim/) důkazu nesplnitelnosti:
+/// přidání, nebo smazání [klauzule](`Clause`) z formule.
+///
+enum ProofStep {
+    Addition(Clause),
+    Deletion(Clause),
+}
+
+/// Výsledek pokusu o [vivifikaci](`Formula::vivify_clauses`) jedné klauzule.
+///
+enum VivifyOutcome {
+    /// Klauzule zůstala beze změny.
+    Unchanged,
+    /// Klauzule vyšla najevo jako už splněná a je možné ji celou zahodit.
+    Subsumed,
+    /// Klauzuli šlo zkrátit na tento seznam literálů.
+    Shortened(Clause),
+}
+
+/// Zachicuje počet prozkoumaných uzlů, počet použití [unit propagace](`Formula::unit_propagate`),
+/// couvací zásobník (`trail`) s dosavadními přiřazeními a jejich rozhodovacími úrovněmi,
+/// pole pravdivostních hodnot indexované proměnnou (`values`), nad kterým [`solve`]
+/// provádí CDCL, a (je-li zapnuté) [zaznamenané kroky DRAT důkazu](`ProofStep`).
 pub struct State {
     pub unit_propagation_counter: usize,
     pub node_counter: usize,
+    /// Počet naučených klauzulí ponechaných v databázi napříč všemi doběhlými
+    /// [redukcemi](`Formula::reduce_learned_clauses`).
+    pub learned_clauses_kept: usize,
+    /// Počet naučených klauzulí smazaných napříč všemi doběhlými
+    /// [redukcemi](`Formula::reduce_learned_clauses`).
+    pub learned_clauses_deleted: usize,
+    /// Počet restartů, viz [`RestartStrategy`].
+    pub restart_counter: usize,
+    /// Počet literálů odstraněných [vivifikací](`Formula::vivify_clauses`) ze zkrácených klauzulí.
+    pub vivified_literals_removed: usize,
+    /// Počet klauzulí zahozených [vivifikací](`Formula::vivify_clauses`), protože vyšly najevo
+    /// jako subsumované.
+    pub vivified_clauses_eliminated: usize,
+    trail: Vec<TrailEntry>,
+    /// Pravdivostní hodnota proměnné `i` je na indexu `i`, `None` pokud je nepřiřazená.
+    /// Nahrazuje dřívější hledání v `trail` a umožňuje vyhodnocení literálu v `O(1)`.
+    values: Vec<Option<bool>>,
+    /// Rozhodovací úroveň, ve které byla proměnná `i` přiřazena. Platná jen pokud
+    /// `values[i]` není `None`.
+    levels: Vec<usize>,
+    /// Aktivita proměnné `i` (VSIDS/LRB) — roste s každým konfliktem, kterého se proměnná
+    /// účastní, a postupně se [odbourává](`State::decay_activity`). Větvící heuristika
+    /// [`State::pick_branch_literal`] vybírá nepřiřazenou proměnnou s nejvyšší hodnotou zde.
+    activity: Vec<f64>,
+    /// Krok přičítaný k [aktivitě](`State::activity`) při [konfliktu](`State::bump_activity`);
+    /// po každém konfliktu se vynásobí `1 / `[`ACTIVITY_DECAY`].
+    activity_increment: f64,
+    /// Binární halda (max-halda podle [`State::activity`]) nepřiřazených proměnných — kořen
+    /// (index `0`) je vždy proměnná s nejvyšší aktivitou. Udržuje [`State::pick_branch_literal`]
+    /// v `O(log n)` namísto lineárního průchodu všemi proměnnými při každém rozhodnutí.
+    order_heap: Vec<usize>,
+    /// Pozice proměnné `i` uvnitř [`State::order_heap`], `None` není-li proměnná v haldě
+    /// (buď je právě přiřazená, nebo o ní halda ještě neví), viz [`State::ensure_heap_variables`].
+    heap_position: Vec<Option<usize>>,
+    /// Poslední polarita, na kterou byla proměnná `i` přiřazena, než byla couvnutím zrušena
+    /// — další rozhodnutí o téže proměnné touto polaritou začíná znovu (*phase saving*).
+    phase: Vec<Option<bool>>,
+    /// Index prvního dosud nepropagovaného literálu v `trail`.
+    propagated: usize,
+    level: usize,
+    /// `None`, pokud zápis DRAT důkazu není zapnutý, viz [`State::enable_proof_logging`].
+    proof: Option<Vec<ProofStep>>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            unit_propagation_counter: 0,
+            node_counter: 0,
+            learned_clauses_kept: 0,
+            learned_clauses_deleted: 0,
+            restart_counter: 0,
+            vivified_literals_removed: 0,
+            vivified_clauses_eliminated: 0,
+            trail: Vec::new(),
+            values: Vec::new(),
+            levels: Vec::new(),
+            activity: Vec::new(),
+            activity_increment: 1.0,
+            order_heap: Vec::new(),
+            heap_position: Vec::new(),
+            phase: Vec::new(),
+            propagated: 0,
+            level: 0,
+            proof: None,
+        }
+    }
+}
+
+impl State {
+    /// Zapne zaznamenávání kroků [DRAT](https://www.cs.utexas.edu/~marijn/drat-trim/) důkazu —
+    /// každá klauzule naučená analýzou konfliktu se od teď zapisuje, aby ji šlo později
+    /// [uložit do souboru](`write_drat_proof`) a nezávisle ověřit, že je výsledek UNSAT korektní.
+    ///
+    pub fn enable_proof_logging(&mut self) {
+        self.proof = Some(Vec::new());
+    }
+
+    /// Zaznamená přidání [klauzule](`Clause`) do [DRAT](https://www.cs.utexas.edu/~marijn/drat-trim/)
+    /// důkazu, pokud je zapnutý.
+    ///
+    fn record_addition(&mut self, clause: Clause) {
+        if let Some(proof) = &mut self.proof {
+            proof.push(ProofStep::Addition(clause));
+        }
+    }
+
+    /// Zaznamená smazání [klauzule](`Clause`) z [DRAT](https://www.cs.utexas.edu/~marijn/drat-trim/)
+    /// důkazu, pokud je zapnutý.
+    ///
+    fn record_deletion(&mut self, clause: Clause) {
+        if let Some(proof) = &mut self.proof {
+            proof.push(ProofStep::Deletion(clause));
+        }
+    }
+
+    /// Vrátí vyhodnocení daného [literálu](`Literal`) v `O(1)` podle [`State::values`],
+    /// pokud už byla jeho proměnná přiřazena.
+    ///
+    fn value(&self, literal: Literal) -> Option<bool> {
+        let positive = self.values.get(variable_of(literal)).copied().flatten()?;
+        Some(positive == (literal > 0))
+    }
+
+    /// Rozhodovací úroveň, ve které byla proměnná daného [literálu](`Literal`) přiřazena.
+    ///
+    fn level_of(&self, literal: Literal) -> usize {
+        self.levels.get(variable_of(literal)).copied().unwrap_or(0)
+    }
+
+    /// Přidá [literál](`Literal`) na [couvací zásobník](`State::trail`) v aktuální rozhodovací
+    /// úrovni a nastaví odpovídající hodnotu v [`State::values`]. `reason` je `None` pro
+    /// rozhodnutí, jinak index klauzule, která literál vynutila.
+    ///
+    fn assign(&mut self, literal: Literal, reason: Option<usize>) {
+        let variable = variable_of(literal);
+        if self.values.len() <= variable {
+            self.values.resize(variable + 1, None);
+            self.levels.resize(variable + 1, 0);
+        }
+        self.values[variable] = Some(literal > 0);
+        self.levels[variable] = self.level;
+        self.heap_remove(variable);
+
+        self.trail.push(TrailEntry {
+            literal,
+            level: self.level,
+            reason,
+        });
+    }
+
+    /// Vrátí [couvací zásobník](`State::trail`) zpět na danou rozhodovací úroveň — odstraní
+    /// všechny záznamy přiřazené v hlubší úrovni a uvolní jejich hodnotu v [`State::values`],
+    /// [uloží si přitom jejich polaritu](`State::phase`) pro příští rozhodnutí o stejné
+    /// proměnné. Nepropagované literály hlubších úrovní se tím také zahodí; nepropagované
+    /// literály zachovaných úrovní (např. právě asertovaný literál na úrovni `0`) čekají dál.
+    ///
+    fn backjump(&mut self, level: usize) {
+        while let Some(entry) = self.trail.last() {
+            if entry.level <= level {
+                break;
+            }
+            let variable = variable_of(entry.literal);
+            self.values[variable] = None;
+            if self.phase.len() <= variable {
+                self.phase.resize(variable + 1, None);
+            }
+            self.phase[variable] = Some(entry.literal > 0);
+            if variable < self.heap_position.len() {
+                self.heap_push(variable);
+            }
+            self.trail.pop();
+        }
+        self.level = level;
+        self.propagated = self.propagated.min(self.trail.len());
+    }
+
+    /// Navýší [aktivitu](`State::activity`) proměnné o aktuální [krok](`State::activity_increment`)
+    /// — volá se pro každou proměnnou zapojenou do [analýzy konfliktu](`Formula::analyze_conflict`).
+    /// Překročí-li aktivita [práh](`ACTIVITY_RESCALE_THRESHOLD`), přeškáluje všechny aktivity
+    /// i krok dolů, aby nepřetekly `f64`.
+    ///
+    fn bump_activity(&mut self, variable: usize) {
+        if self.activity.len() <= variable {
+            self.activity.resize(variable + 1, 0.0);
+        }
+        self.activity[variable] += self.activity_increment;
+
+        if self.activity[variable] > ACTIVITY_RESCALE_THRESHOLD {
+            for value in &mut self.activity {
+                *value *= 1.0 / ACTIVITY_RESCALE_THRESHOLD;
+            }
+            self.activity_increment *= 1.0 / ACTIVITY_RESCALE_THRESHOLD;
+        }
+
+        if let Some(position) = self.heap_position.get(variable).copied().flatten() {
+            self.heap_sift_up(position);
+        }
+    }
+
+    /// Po konfliktu zvětší krok přičítaný budoucím [`State::bump_activity`] voláním vynásobením
+    /// `1 / `[`ACTIVITY_DECAY`], takže proměnné zapojené do novějších konfliktů postupně
+    /// převáží ty ze starších.
+    ///
+    fn decay_activity(&mut self) {
+        self.activity_increment *= 1.0 / ACTIVITY_DECAY;
+    }
+
+    /// Vybere nepřiřazenou proměnnou s nejvyšší [aktivitou](`State::activity`) (VSIDS/LRB) —
+    /// kořen [`State::order_heap`], tedy v `O(log n)` namísto lineárního průchodu všemi
+    /// proměnnými — a vrátí ji jako literál s polaritou podle [uložené fáze](`State::phase`)
+    /// z posledního couvnutí — nemá-li ještě žádnou, rozhodne kladně.
+    ///
+    fn pick_branch_literal(&mut self, nvars: usize) -> Literal {
+        self.ensure_heap_variables(nvars);
+
+        let &variable = self
+            .order_heap
+            .first()
+            .expect("vybírá se jen tehdy, když formule ještě není splněná");
+
+        if self.phase.get(variable).copied().flatten() == Some(false) {
+            -(variable as Literal)
+        } else {
+            variable as Literal
+        }
+    }
+
+    /// Zaregistruje proměnné `1..=nvars`, které [`State::order_heap`] ještě nezná (typicky se
+    /// objevily až v nově přidaných klauzulích), jako nepřiřazené — do haldy se zařadí podle
+    /// aktivity, kterou případně už nasbíraly v analýze konfliktu, jinak nulové.
+    ///
+    fn ensure_heap_variables(&mut self, nvars: usize) {
+        let known = self.heap_position.len().saturating_sub(1);
+        if known >= nvars {
+            return;
+        }
+
+        for variable in (known + 1).max(1)..=nvars {
+            if self.value(variable as Literal).is_none() {
+                self.heap_push(variable);
+            } else if self.heap_position.len() <= variable {
+                self.heap_position.resize(variable + 1, None);
+            }
+        }
+    }
+
+    /// Aktivita proměnné `i`, nebo `0.0`, pokud [`State::activity`] o ní ještě neví.
+    ///
+    fn activity_of(&self, variable: usize) -> f64 {
+        self.activity.get(variable).copied().unwrap_or(0.0)
+    }
+
+    /// Vloží proměnnou do [`State::order_heap`] a vybublá ji na pozici odpovídající její
+    /// [aktivitě](`State::activity`). Je-li proměnná už v haldě, nedělá nic.
+    ///
+    fn heap_push(&mut self, variable: usize) {
+        if self.heap_position.len() <= variable {
+            self.heap_position.resize(variable + 1, None);
+        }
+        if self.heap_position[variable].is_some() {
+            return;
+        }
+
+        let position = self.order_heap.len();
+        self.order_heap.push(variable);
+        self.heap_position[variable] = Some(position);
+        self.heap_sift_up(position);
+    }
+
+    /// Odstraní proměnnou z [`State::order_heap`], je-li v něm — volá [`State::assign`] při
+    /// přiřazení proměnné, protože rozhodnutí/propagace ji dočasně vyřazuje z výběru
+    /// [`State::pick_branch_literal`].
+    ///
+    fn heap_remove(&mut self, variable: usize) {
+        let Some(position) = self.heap_position.get(variable).copied().flatten() else {
+            return;
+        };
+
+        let last = self.order_heap.len() - 1;
+        self.heap_swap(position, last);
+        self.order_heap.pop();
+        self.heap_position[variable] = None;
+
+        if position < self.order_heap.len() {
+            self.heap_sift_up(position);
+            self.heap_sift_down(position);
+        }
+    }
+
+    /// Prohodí dva prvky [`State::order_heap`] a udržuje [`State::heap_position`] v souladu.
+    ///
+    fn heap_swap(&mut self, a: usize, b: usize) {
+        self.order_heap.swap(a, b);
+        self.heap_position[self.order_heap[a]] = Some(a);
+        self.heap_position[self.order_heap[b]] = Some(b);
+    }
+
+    /// Vybublá prvek na dané pozici [`State::order_heap`] směrem ke kořeni, dokud je jeho
+    /// [aktivita](`State::activity`) vyšší než aktivita rodiče.
+    ///
+    fn heap_sift_up(&mut self, mut position: usize) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if self.activity_of(self.order_heap[parent]) >= self.activity_of(self.order_heap[position]) {
+                break;
+            }
+            self.heap_swap(parent, position);
+            position = parent;
+        }
+    }
+
+    /// Probublá prvek na dané pozici [`State::order_heap`] směrem od kořene, dokud má některé
+    /// z jeho dětí vyšší [aktivitu](`State::activity`).
+    ///
+    fn heap_sift_down(&mut self, mut position: usize) {
+        loop {
+            let left = 2 * position + 1;
+            let right = 2 * position + 2;
+            let mut largest = position;
+
+            if left < self.order_heap.len() && self.activity_of(self.order_heap[left]) > self.activity_of(self.order_heap[largest]) {
+                largest = left;
+            }
+            if right < self.order_heap.len() && self.activity_of(self.order_heap[right]) > self.activity_of(self.order_heap[largest]) {
+                largest = right;
+            }
+            if largest == position {
+                break;
+            }
+
+            self.heap_swap(position, largest);
+            position = largest;
+        }
+    }
 }
 
 impl Formula {
-    /// Vrátí kopii [formule](`Formula`), ve které má daný [literál](`Literal`)
-    /// přiřazené pravdivé [vyhodnocení](`Assignment`).
+    /// Přidá původní [klauzuli](`Clause`) ze vstupního souboru do formule.
     ///
-    fn with_true(&self, literal: Literal) -> Self {
-        let mut new = self.clone();
-        new.assign_true(literal);
-        new
+    fn add_clause(&mut self, literals: Clause) -> usize {
+        self.add_clause_with_origin(literals, ClauseOrigin::Original)
     }
 
-    /// V dané [formuli](`Formula`) přiřadí danému [literálu](`Literal`) pravdivé
-    /// [vyhodnocení](`Assignment`).
+    /// Přidá [klauzuli](`Clause`) naučenou [analýzou konfliktu](`Formula::analyze_conflict`)
+    /// s daným LBD (glue) — počtem rozdílných rozhodovacích úrovní mezi jejími literály
+    /// v momentě naučení, viz [`Formula::reduce_learned_clauses`].
     ///
-    fn assign_true(&mut self, literal: Literal) {
-        self.assignments.push(literal);
-        self.clauses.retain(|clause| !clause.contains(&literal));
+    /// Je třeba předat ji s asertovaným literálem na indexu `0`, aby sledování zůstalo platné.
+    ///
+    fn add_learned_clause(&mut self, literals: Clause, lbd: usize) -> usize {
+        self.add_clause_with_origin(literals, ClauseOrigin::Learned { lbd })
+    }
 
-        let inverse = -literal;
-        for clause in &mut self.clauses {
-            if let Some(i) = clause.iter().position(|lit| lit == &inverse) {
-                clause.swap_remove(i);
+    /// Přidá [klauzuli](`Clause`) do formule a zaregistruje její sledované literály
+    /// (první dva literály klauzule; u jednoliterálové klauzule se sleduje ten jediný).
+    /// Vrátí index nové klauzule.
+    ///
+    fn add_clause_with_origin(&mut self, literals: Clause, origin: ClauseOrigin) -> usize {
+        let index = self.clauses.len();
+
+        for &literal in literals.iter() {
+            self.nvars = self.nvars.max(variable_of(literal));
+        }
+
+        let positions = if literals.len() >= 2 { [0, 1] } else { [0, 0] };
+        if !literals.is_empty() {
+            self.watches.entry(literals[positions[0]]).or_default().push(index);
+            if positions[1] != positions[0] {
+                self.watches.entry(literals[positions[1]]).or_default().push(index);
             }
+        } else {
+            self.has_empty_clause = true;
         }
+
+        self.watched.push(positions);
+        self.clauses.push(literals);
+        self.origin.push(origin);
+        self.deleted.push(false);
+        index
     }
 
-    /// Najde všechny [klauzule](`Clause`) délky **1** a přiřadí jejich
-    /// literálům pravdivé [vyhodnocení](`Assignment`).
+    /// Smaže zhruba polovinu naučených klauzulí z databáze: z kandidátů (naučené klauzule,
+    /// které zrovna nejsou reason žádného přiřazení na [`State::trail`]) ponechá ty s nejmenším
+    /// LBD (glue) — tedy ty, u kterých při naučení stačilo nejméně rozdílných rozhodovacích
+    /// úrovní. Původní klauzule ze vstupního souboru se nikdy nemažou. Smazané klauzule zůstanou
+    /// na svém indexu (viz [`Formula`]) s příznakem `deleted` a jsou odstraněny ze sledování,
+    /// takže je propagace ani analýza konfliktu dál nenavštíví. Každé smazání [se zapíše do
+    /// DRAT důkazu](`State::record_deletion`) a započítá do [`State::learned_clauses_deleted`].
     ///
-    fn unit_propagate(&mut self, state: &mut State) {
-        let mut assign_to_true: Vec<Literal> = self
-            .clauses
+    fn reduce_learned_clauses(&mut self, state: &mut State) {
+        let reasons: hashbrown::HashSet<usize> = state.trail.iter().filter_map(|entry| entry.reason).collect();
+
+        let mut candidates: Vec<(usize, usize)> = self
+            .origin
             .iter()
-            .filter(|clause| clause.len() == 1)
-            .map(|unit| unit[0])
+            .enumerate()
+            .filter_map(|(index, &origin)| match origin {
+                ClauseOrigin::Learned { lbd } if !self.deleted[index] && !reasons.contains(&index) => Some((index, lbd)),
+                _ => None,
+            })
             .collect();
-        assign_to_true.sort();
-        assign_to_true.dedup();
+        candidates.sort_by_key(|&(_, lbd)| lbd);
 
-        state.unit_propagation_counter += assign_to_true.len();
-        for literal in assign_to_true {
-            self.assign_true(literal);
+        let to_delete = candidates.len() / 2;
+        for &(index, _) in &candidates[candidates.len() - to_delete..] {
+            self.delete_clause(state, index);
+            state.learned_clauses_deleted += 1;
         }
+
+        state.learned_clauses_kept += candidates.len() - to_delete;
     }
 
-    /// Hledá [literál](`Literal`), který se vyskytuje najčastěji v [klauzilých](`Clause`) minimální délky.
+    /// Odstraní sledované literály klauzule na daném indexu z [`Formula::watches`], aniž by se
+    /// klauzule fyzicky mazala nebo měnil její index, viz [`Formula`].
     ///
-    /// Délkou myslíme počet [literálů](`Literal`) v dané [klauzuli](`Clause`).
+    fn remove_watches(&mut self, index: usize) {
+        for &slot in &self.watched[index] {
+            let literal = self.clauses[index][slot];
+            if let Some(watchers) = self.watches.get_mut(&literal) {
+                watchers.retain(|&watcher| watcher != index);
+            }
+        }
+    }
+
+    /// Smaže klauzuli na daném indexu z databáze: odstraní ji ze sledování, [zapíše smazání do
+    /// DRAT důkazu](`State::record_deletion`) a nastaví jí příznak `deleted` (viz [`Formula`]).
+    /// Klauzule si index ponechá, aby případné reason odkazy na ni zůstaly platné.
     ///
-    /// - Zjistí minimální délku [klauzilí](`Cluase`) ve [formuli](`Formula`).
+    fn delete_clause(&mut self, state: &mut State, index: usize) {
+        self.remove_watches(index);
+        state.record_deletion(self.clauses[index].clone());
+        self.deleted[index] = true;
+    }
+
+    /// Nahradí obsah klauzule na daném indexu kratším seznamem literálů a znovu ji zaregistruje
+    /// ve sledování se stejným indexem, viz [`Formula::vivify_clauses`].
     ///
-    /// - Vrátí [literál](`Literal`), který se ve všech [klauzulích](`Clause`) té délky vyskytuje nejčastěji.
+    fn rewrite_clause(&mut self, index: usize, literals: Clause) {
+        self.remove_watches(index);
+
+        let positions = if literals.len() >= 2 { [0, 1] } else { [0, 0] };
+        self.watches.entry(literals[positions[0]]).or_default().push(index);
+        if positions[1] != positions[0] {
+            self.watches.entry(literals[positions[1]]).or_default().push(index);
+        }
+
+        self.watched[index] = positions;
+        self.clauses[index] = literals;
+    }
+
+    /// Výsledek pokusu o [vivifikaci](`Formula::vivify_clauses`) jedné klauzule.
     ///
-    fn mom(&self) -> Literal {
-        let min_len = self
+    fn vivify_clause(&mut self, state: &mut State, index: usize) -> VivifyOutcome {
+        let literals = self.clauses[index].clone();
+        if literals.iter().any(|&literal| state.value(literal) == Some(true)) {
+            return VivifyOutcome::Subsumed;
+        }
+
+        // Klauzule se dočasně odpojí od sledování, aby propagace níže mohla zjistit konflikt
+        // nebo vynucení jen z ostatních klauzulí formule — jinak by se vlastní (dosud
+        // nerozhodnutý) literál klauzule uměl sám propagovat na pravdu a falešně předstírat
+        // subsumpci, kterou jiné klauzule vůbec nezaručují.
+        self.remove_watches(index);
+
+        let mut assumed = Vec::new();
+        let mut outcome = VivifyOutcome::Unchanged;
+
+        for &literal in &literals {
+            if state.value(literal) == Some(false) {
+                continue;
+            }
+
+            state.level += 1;
+            state.assign(-literal, None);
+            assumed.push(literal);
+
+            if self.unit_propagate(state).is_some() {
+                outcome = if assumed.len() < literals.len() {
+                    VivifyOutcome::Shortened(assumed)
+                } else {
+                    VivifyOutcome::Unchanged
+                };
+                break;
+            }
+
+            if literals.iter().any(|&other| other != literal && state.value(other) == Some(true)) {
+                outcome = VivifyOutcome::Subsumed;
+                break;
+            }
+        }
+
+        state.backjump(0);
+
+        if matches!(outcome, VivifyOutcome::Unchanged) {
+            let positions = self.watched[index];
+            self.watches.entry(literals[positions[0]]).or_default().push(index);
+            if positions[1] != positions[0] {
+                self.watches.entry(literals[positions[1]]).or_default().push(index);
+            }
+        }
+
+        outcome
+    }
+
+    /// Provede jeden průchod vivifikace: pro každou nemazanou klauzuli o délce alespoň dva,
+    /// která zrovna neslouží jako reason žádného přiřazení na [`State::trail`], postupně
+    /// předpokládá negace jejích literálů pod [unit propagací](`Formula::unit_propagate`) na
+    /// rozhodovací úrovni `0`. Narazí-li propagace na konflikt dřív, než stihla předpokládat
+    /// všechny literály, stačily k němu dosavadní předpoklady — klauzule se zkrátí jen na ně.
+    /// Vynutí-li propagace naopak pravdivost jiného literálu téže klauzule, je klauzule už
+    /// beztak splněná (subsumovaná) a zahodí se celá. Obojí [se zapíše do DRAT
+    /// důkazu](`State::record_addition`) a započítá do [`State::vivified_literals_removed`]/
+    /// [`State::vivified_clauses_eliminated`].
+    ///
+    fn vivify_clauses(&mut self, state: &mut State) {
+        state.backjump(0);
+        let reasons: hashbrown::HashSet<usize> = state.trail.iter().filter_map(|entry| entry.reason).collect();
+
+        let candidates: Vec<usize> = self
             .clauses
             .iter()
-            .map(|clause| clause.len())
-            .min()
-            .unwrap();
-
-        let mut counts = hashbrown::HashMap::new();
-        for shortest_clause in self.clauses.iter().filter(|clause| clause.len() == min_len) {
-            for literal in shortest_clause {
-                if let Some(value) = counts.get_mut(literal) {
-                    *value += 1;
-                } else {
-                    counts.insert(literal, 0usize);
+            .enumerate()
+            .filter(|&(index, clause)| !self.deleted[index] && clause.len() > 1 && !reasons.contains(&index))
+            .map(|(index, _)| index)
+            .collect();
+
+        for index in candidates {
+            if self.deleted[index] {
+                continue;
+            }
+
+            match self.vivify_clause(state, index) {
+                VivifyOutcome::Unchanged => {}
+                VivifyOutcome::Subsumed => {
+                    self.delete_clause(state, index);
+                    state.vivified_clauses_eliminated += 1;
+                }
+                VivifyOutcome::Shortened(shortened) => {
+                    state.vivified_literals_removed += self.clauses[index].len() - shortened.len();
+                    state.record_deletion(self.clauses[index].clone());
+                    state.record_addition(shortened.clone());
+                    self.rewrite_clause(index, shortened);
                 }
             }
         }
+    }
+
+    /// Vyřeší dopady toho, že se `literal` právě stal pravdivým (tedy `-literal` nepravdivým)
+    /// nad klauzulemi, které `-literal` sledují: pro každou buď přesune sledování na jiný
+    /// literál, který není nepravdivý, nebo — pokud žádný takový není — buď jednotkově
+    /// přiřadí druhý sledovaný literál, nebo (je-li i ten nepravdivý) nahlásí konflikt.
+    ///
+    /// Vrátí index konfliktní klauzule, pokud k němu došlo.
+    ///
+    fn propagate_literal(&mut self, state: &mut State, literal: Literal) -> Option<usize> {
+        let false_literal = -literal;
+        let mut watchers = self.watches.remove(&false_literal).unwrap_or_default();
+        let mut conflict = None;
+        let mut index = 0;
+
+        while index < watchers.len() {
+            let clause_index = watchers[index];
+            let watched = self.watched[clause_index];
+            let slot = if self.clauses[clause_index][watched[0]] == false_literal { 0 } else { 1 };
+            let other_position = watched[1 - slot];
+            let other_literal = self.clauses[clause_index][other_position];
+
+            if state.value(other_literal) == Some(true) {
+                index += 1;
+                continue;
+            }
 
-        let (literal, _) = counts.into_iter().max_by_key(|x| x.1).unwrap();
+            let replacement = self.clauses[clause_index]
+                .iter()
+                .enumerate()
+                .find(|&(position, &lit)| position != other_position && state.value(lit) != Some(false))
+                .map(|(position, _)| position);
 
-        *literal
+            if let Some(new_position) = replacement {
+                let new_literal = self.clauses[clause_index][new_position];
+                self.watched[clause_index][slot] = new_position;
+                self.watches.entry(new_literal).or_default().push(clause_index);
+                watchers.swap_remove(index);
+            } else if state.value(other_literal) == Some(false) {
+                conflict = Some(clause_index);
+                break;
+            } else {
+                state.assign(other_literal, Some(clause_index));
+                state.unit_propagation_counter += 1;
+                index += 1;
+            }
+        }
+
+        self.watches.entry(false_literal).or_default().extend(watchers);
+        conflict
+    }
+
+    /// Propaguje postupně všechny dosud nepropagované literály z [`State::trail`]
+    /// (viz [`Formula::propagate_literal`]) a případně nově odvozené literály, které
+    /// propagace sama přidá, dokud se fronta nevyprázdní nebo nenarazí na konflikt.
+    ///
+    /// Vrátí index konfliktní [klauzule](`Clause`), pokud k němu propagací došlo.
+    ///
+    fn unit_propagate(&mut self, state: &mut State) -> Option<usize> {
+        while state.propagated < state.trail.len() {
+            let literal = state.trail[state.propagated].literal;
+            state.propagated += 1;
+
+            if let Some(conflict) = self.propagate_literal(state, literal) {
+                return Some(conflict);
+            }
+        }
+
+        None
+    }
+
+    /// `true`, pokud má každá [klauzule](`Clause`) formule alespoň jeden literál
+    /// vyhodnocený na pravdu. Nemůžeme se spolehnout na to, že jsou přiřazeny všechny
+    /// proměnné — některé se mohou stát nepodstatnými (vyskytují se jen v již splněných
+    /// klauzulích) dřív, než na ně dojde řada při rozhodování.
+    ///
+    fn is_satisfied(&self, state: &State) -> bool {
+        self.clauses
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| !self.deleted[index])
+            .all(|(_, clause)| clause.iter().any(|&literal| state.value(literal) == Some(true)))
+    }
+
+    /// Provede analýzu konfliktu metodou prvního UIP (*unique implication point*): vychází
+    /// z konfliktní [klauzule](`Clause`) a opakovaně ji rozkládá (resolvuje) s klauzulí-důvodem
+    /// naposledy přiřazeného literálu aktuální rozhodovací úrovně, dokud v ní nezbyde právě
+    /// jeden literál z této úrovně — ten je "unikátním implikačním bodem". Výsledný rozklad je
+    /// naučená klauzule.
+    ///
+    /// Vrátí naučenou [klauzuli](`Clause`) — s asertovaným literálem na indexu `0` a literálem
+    /// druhé nejvyšší úrovně na indexu `1`, aby ji šlo rovnou předat [`Formula::add_learned_clause`] —
+    /// spolu s úrovní, na kterou se má couvnout.
+    ///
+    /// Zároveň [navýší aktivitu](`State::bump_activity`) každé proměnné, která se při
+    /// rozkládání objeví (VSIDS/LRB, viz [`State::pick_branch_literal`]), a [krok aktivity
+    /// zvětší](`State::decay_activity`) pro příští konflikt.
+    ///
+    fn analyze_conflict(&self, state: &mut State, conflict: usize) -> (Clause, usize) {
+        let mut learned = self.clauses[conflict].clone();
+        for &literal in &learned {
+            state.bump_activity(variable_of(literal));
+        }
+        let mut at_current_level = learned
+            .iter()
+            .filter(|&&literal| state.level_of(literal) == state.level)
+            .count();
+
+        let mut trail_index = state.trail.len();
+        while at_current_level > 1 && trail_index > 0 {
+            trail_index -= 1;
+            let entry = state.trail[trail_index];
+            if entry.level != state.level {
+                continue;
+            }
+
+            let Some(position) = learned.iter().position(|&literal| literal == -entry.literal) else {
+                continue;
+            };
+            let Some(reason) = entry.reason else {
+                continue;
+            };
+
+            learned.swap_remove(position);
+            at_current_level -= 1;
+
+            for &literal in &self.clauses[reason] {
+                state.bump_activity(variable_of(literal));
+                if literal == entry.literal || learned.contains(&literal) {
+                    continue;
+                }
+                if state.level_of(literal) == state.level {
+                    at_current_level += 1;
+                }
+                learned.push(literal);
+            }
+        }
+
+        let asserting_position = learned
+            .iter()
+            .position(|&literal| state.level_of(literal) == state.level)
+            .expect("naučená klauzule musí obsahovat literál aktuální úrovně");
+        learned.swap(0, asserting_position);
+
+        let backjump_level = learned[1..]
+            .iter()
+            .map(|&literal| state.level_of(literal))
+            .max()
+            .unwrap_or(0);
+
+        if learned.len() > 1 {
+            let second_position = 1 + learned[1..]
+                .iter()
+                .position(|&literal| state.level_of(literal) == backjump_level)
+                .unwrap_or(0);
+            learned.swap(1, second_position);
+        }
+
+        state.decay_activity();
+
+        (learned, backjump_level)
+    }
+
+    /// Rozloží konfliktní [klauzuli](`Clause`) narazenou na rozhodovací úrovni `0` až do
+    /// prázdné klauzule: opakovaně ji resolvuje s klauzulí-důvodem naposledy přiřazeného
+    /// literálu z [`State::trail`], dokud v ní nezbyde žádný literál. Na rozdíl od
+    /// [`Formula::analyze_conflict`] nekončí u prvního UIP, protože na úrovni `0` není žádné
+    /// rozhodnutí, ke kterému by šlo couvnout — konflikt dokazuje přímo nesplnitelnost celé
+    /// formule, takže výsledná prázdná klauzule je zakončovací krok [DRAT](`write_drat_proof`)
+    /// důkazu.
+    ///
+    fn analyze_top_level_conflict(&self, state: &State, conflict: usize) -> Clause {
+        let mut learned = self.clauses[conflict].clone();
+        let mut trail_index = state.trail.len();
+
+        while !learned.is_empty() && trail_index > 0 {
+            trail_index -= 1;
+            let entry = state.trail[trail_index];
+
+            let Some(position) = learned.iter().position(|&literal| literal == -entry.literal) else {
+                continue;
+            };
+            let Some(reason) = entry.reason else {
+                break;
+            };
+
+            learned.swap_remove(position);
+            for &literal in &self.clauses[reason] {
+                if literal != entry.literal && !learned.contains(&literal) {
+                    learned.push(literal);
+                }
+            }
+        }
+
+        learned
     }
 }
 
@@ -156,58 +951,331 @@ impl Formula {
 ///
 pub type SatResult = Option<Vec<Assignment>>;
 
-/// Rekurzivně prozkoumává strom možných [přiřazení](`Assignment`). Průběžně jej ořezává pomocí
-/// [unit propagace](`Formula::unit_propagate`).
-///
-/// Postupuje takto:
-///
-/// 1. Pokusí se ořezat strom k prozkoumání [unit propagací](`Formula::unit_propagate`)
-///
-/// 2. Zkontroluje, jestli už nemáme splněno (t.j. [seznam klauzilí formule](`Formula`) je prázdný). Pokud ano, vrátí [seznam literálů s pravdivým
-///    vyhodnocením](`Assignment`).
+/// Výsledek [`Solver::solve_under_assumptions`]: buď splňující přiřazení, nebo ta podmnožina
+/// zadaných předpokladů, která za nesplnitelností doopravdy stála (tzv. "failed"/"core"
+/// předpoklady) — prázdná, pokud je nesplnitelná už samotná formule bez předpokladů.
 ///
-/// 3. Zkontroluje, jestli existují již nesplintelné klauzule. Pokud ano, ukončíme naše
-///    prozkoumávání tohoto podtromu.
-///
-/// 4. Použije heuristiku [**M**ost **O**ccurences in clauses of **M**inimal length](`Formula::mom`) k výběru
-///    ([literálu](`Literal`)) příští větve k prozkoumání.
-///
-/// 5. Na konec napřed zkoumá podstrom, ve kterém je vybraný [literál](`Literal`) vyhodnocený na pravdu.
-///    Když nenajde vyhovující [přiřazení](`Assignment`), pak zkusí zkoumat podstrom, ve kterém je vybraný
-///    [literál](`Literal`) vyhodnocený na nepravdu.
+pub type AssumptionResult = Result<Vec<Assignment>, Vec<Assignment>>;
+
+impl Formula {
+    /// Zjistí, proč je daný literál momentálně nepravdivý: opakovaně rozkládá klauzule-důvody
+    /// literálů na cestě zpět k jeho negaci, dokud nezůstanou jen literály bez důvodu (tedy
+    /// rozhodnutí nebo [předpoklady](`Solver::solve_under_assumptions`)). Používá se, když
+    /// předpoklad přímo odporuje už ustálenému přiřazení, tedy dřív, než na něj vůbec dojde
+    /// řada při běžné analýze konfliktu.
+    ///
+    fn explain(&self, state: &State, literal: Literal) -> Vec<Literal> {
+        let mut pending = vec![-literal];
+        let mut seen = hashbrown::HashSet::new();
+        let mut blocking = Vec::new();
+
+        while let Some(true_literal) = pending.pop() {
+            if !seen.insert(true_literal) {
+                continue;
+            }
+
+            let reason = state
+                .trail
+                .iter()
+                .find(|entry| entry.literal == true_literal)
+                .and_then(|entry| entry.reason);
+
+            match reason {
+                Some(reason) => {
+                    for &other in &self.clauses[reason] {
+                        if other != true_literal {
+                            pending.push(-other);
+                        }
+                    }
+                }
+                None => blocking.push(true_literal),
+            }
+        }
+
+        blocking
+    }
+}
+
+/// Udržuje [formuli](`Formula`) a stav hledání ([`State`]) pohromadě mezi opakovanými
+/// dotazy [`Solver::solve_under_assumptions`], takže naučené klauzule, sledované literály
+/// a statistiky zůstávají zachovány napříč voláními — formule se nenačítá ani neinicializuje
+/// znovu, mění se jen sada předpokladů.
 ///
-pub fn solve(state: &mut State, mut formula: Formula) -> SatResult {
-    state.node_counter += 1;
+pub struct Solver {
+    formula: Formula,
+    state: State,
+    /// Celkový počet konfliktů (nenuluje se) — porovnává se s [`Solver::next_reduction`] i
+    /// [`Solver::next_vivification`].
+    conflicts: usize,
+    /// Počet konfliktů, po kterém proběhne příští redukce — po každé redukci geometricky roste.
+    next_reduction: usize,
+    /// Zapnutá [strategie rozvrhování restartů](`RestartStrategy`), `None` restarty vypíná.
+    restart_strategy: Option<RestartStrategy>,
+    /// Počet konfliktů od posledního restartu.
+    conflicts_since_restart: usize,
+    /// Pořadí příštího restartu v [Lubyho posloupnosti](`luby`) pro [`RestartStrategy::Luby`].
+    luby_index: usize,
+    /// LBD posledních [`DYNAMIC_RESTART_WINDOW`] naučených klauzulí pro [`RestartStrategy::Dynamic`].
+    recent_lbd: VecDeque<usize>,
+    /// Součet [`Solver::recent_lbd`], aby se krátkodobý průměr nemusel počítat průchodem fronty.
+    recent_lbd_sum: usize,
+    /// Součet LBD všech dosud naučených klauzulí, pro celkový průměr v [`RestartStrategy::Dynamic`].
+    total_lbd_sum: usize,
+    /// Počet dosud naučených klauzulí, pro celkový průměr v [`RestartStrategy::Dynamic`].
+    total_lbd_count: usize,
+    /// Klouzavý průměr délky `trail` v době konfliktu, pro blokování restartů v neobvykle
+    /// hlubokém stavu ([`DYNAMIC_RESTART_TRAIL_BLOCK_FACTOR`]).
+    trail_length_ema: f64,
+    /// `true`, pokud je zapnutá periodická [vivifikace](`Formula::vivify_clauses`).
+    vivify_enabled: bool,
+    /// Počet konfliktů, po kterém proběhne příští vivifikace — po každé vivifikaci geometricky roste.
+    next_vivification: usize,
+}
 
-    formula.unit_propagate(state);
+impl Solver {
+    /// Vytvoří řešitel nad danou formulí s čerstvým [`State`] a vypnutými restarty.
+    ///
+    pub fn new(formula: Formula) -> Self {
+        Self {
+            formula,
+            state: State::default(),
+            conflicts: 0,
+            next_reduction: INITIAL_REDUCTION_BUDGET,
+            restart_strategy: None,
+            conflicts_since_restart: 0,
+            luby_index: 1,
+            recent_lbd: VecDeque::new(),
+            recent_lbd_sum: 0,
+            total_lbd_sum: 0,
+            total_lbd_count: 0,
+            trail_length_ema: 0.0,
+            vivify_enabled: false,
+            next_vivification: INITIAL_VIVIFICATION_BUDGET,
+        }
+    }
+
+    /// Zapne [rozvrhování restartů](`RestartStrategy`) danou strategií.
+    ///
+    pub fn set_restart_strategy(&mut self, strategy: RestartStrategy) {
+        self.restart_strategy = Some(strategy);
+    }
+
+    /// Zapne [zaznamenávání DRAT důkazu](`State::enable_proof_logging`) pro tento řešitel.
+    ///
+    pub fn enable_proof_logging(&mut self) {
+        self.state.enable_proof_logging();
+    }
 
-    if formula.clauses.is_empty() {
-        return Some(formula.assignments);
+    /// Zapne periodickou [vivifikaci](`Formula::vivify_clauses`) mezi konflikty.
+    ///
+    pub fn enable_vivification(&mut self) {
+        self.vivify_enabled = true;
     }
-    if formula
-        .clauses
-        .iter()
-        .find(|clause| clause.is_empty())
-        .is_some()
-    {
-        return None;
+
+    /// Statistiky a couvací zásobník dosavadního hledání.
+    ///
+    pub fn state(&self) -> &State {
+        &self.state
     }
 
-    let literal = formula.mom();
+    /// Rozhodne, jestli má podle zapnuté [`RestartStrategy`] proběhnout restart — tedy couvnutí
+    /// celého `trail` zpět na úroveň `0` při zachování naučených klauzulí i aktivit proměnných.
+    ///
+    fn should_restart(&self) -> bool {
+        match self.restart_strategy {
+            None => false,
+            Some(RestartStrategy::Luby) => self.conflicts_since_restart >= LUBY_UNIT * luby(self.luby_index),
+            Some(RestartStrategy::Dynamic) => {
+                if self.recent_lbd.len() < DYNAMIC_RESTART_WINDOW || self.total_lbd_count == 0 {
+                    return false;
+                }
+
+                if self.state.trail.len() as f64 > self.trail_length_ema * DYNAMIC_RESTART_TRAIL_BLOCK_FACTOR {
+                    return false;
+                }
+
+                let recent_average = self.recent_lbd_sum as f64 / DYNAMIC_RESTART_WINDOW as f64;
+                let total_average = self.total_lbd_sum as f64 / self.total_lbd_count as f64;
+                recent_average > DYNAMIC_RESTART_LBD_FACTOR * total_average
+            }
+        }
+    }
+
+    /// Zaznamená LBD nově naučené klauzule do [`Solver::recent_lbd`]/[`Solver::total_lbd_sum`]
+    /// a [`Solver::trail_length_ema`] do klouzavého průměru — podklady pro [`Solver::should_restart`].
+    /// Je-li čas na restart, couvne `trail` na úroveň `0` a [započítá ho](`State::restart_counter`).
+    ///
+    fn record_conflict_for_restart(&mut self, lbd: usize) {
+        self.conflicts_since_restart += 1;
+        self.total_lbd_sum += lbd;
+        self.total_lbd_count += 1;
+
+        self.recent_lbd.push_back(lbd);
+        self.recent_lbd_sum += lbd;
+        if self.recent_lbd.len() > DYNAMIC_RESTART_WINDOW {
+            self.recent_lbd_sum -= self.recent_lbd.pop_front().unwrap();
+        }
 
-    return solve(state, formula.with_true(literal))
-        .or_else(|| solve(state, formula.with_true(-literal)));
+        self.trail_length_ema = 0.95 * self.trail_length_ema + 0.05 * self.state.trail.len() as f64;
+
+        if self.should_restart() {
+            self.state.backjump(0);
+            self.state.restart_counter += 1;
+            self.conflicts_since_restart = 0;
+            self.luby_index += 1;
+        }
+    }
+
+    /// Projde `assumptions` a vrátí `Some(Ok(literal))` pro první dosud nepřiřazený předpoklad
+    /// (další rozhodnutí), `Some(Err(core))`, pokud je nějaký předpoklad už nepravdivý (formule
+    /// je pod danými předpoklady nesplnitelná — `core` je [vysvětlení](`Formula::explain`)
+    /// omezené na předpoklady, doplněné o samotný nepravdivý předpoklad), nebo `None`, pokud jsou už všechny předpoklady pravdivé. Dokud
+    /// nevrátí `None`, nesmí se formule prohlašovat za splněnou — mohla se jen shodou okolností
+    /// splnit bez toho, aby některý předpoklad vůbec dostal svou rozhodovací úroveň.
+    ///
+    fn pending_assumption(&self, assumptions: &[Literal], assumption_vars: &hashbrown::HashSet<usize>) -> Option<Result<Literal, Vec<Literal>>> {
+        for &literal in assumptions {
+            match self.state.value(literal) {
+                Some(true) => continue,
+                Some(false) => {
+                    let mut core: Vec<Literal> = self
+                        .formula
+                        .explain(&self.state, literal)
+                        .into_iter()
+                        .filter(|literal| assumption_vars.contains(&variable_of(*literal)))
+                        .collect();
+                    core.push(literal);
+                    return Some(Err(core));
+                }
+                None => return Some(Ok(literal)),
+            }
+        }
+
+        None
+    }
+
+    /// Pokusí se formuli splnit s danými `assumptions` vynucenými na pravdu — stejně jako by
+    /// šlo o rozhodnutí na začátku prohledávacího stromu, jen se mezi jednotlivými voláními
+    /// zahazují, zatímco naučené klauzule, sledované literály a statistiky ([`Solver::state`])
+    /// zůstávají. Postupuje jako [`solve`], jen [příští rozhodnutí](`Solver::pending_assumption`)
+    /// nejdřív čerpá z `assumptions`.
+    ///
+    /// Obsahuje-li formule [prázdnou klauzuli](`Formula::has_empty_clause`) (typicky ze vstupního
+    /// souboru), je triviálně nesplnitelná bez ohledu na `assumptions` — vrátí se rovnou, aniž
+    /// by vůbec došlo na [výběr rozhodnutí](`State::pick_branch_literal`), které by jinak časem
+    /// selhalo, protože prázdná klauzule se nedá [splnit](`Formula::is_satisfied`) a zároveň
+    /// nemá co [sledovat](`Formula::add_clause_with_origin`).
+    ///
+    pub fn solve_under_assumptions(&mut self, assumptions: &[Assignment]) -> AssumptionResult {
+        self.state.backjump(0);
+
+        if self.formula.has_empty_clause {
+            return Err(Vec::new());
+        }
+
+        let assumption_vars: hashbrown::HashSet<usize> = assumptions.iter().map(|&literal| variable_of(literal)).collect();
+
+        loop {
+            if let Some(conflict) = self.formula.unit_propagate(&mut self.state) {
+                if self.state.level == 0 {
+                    let empty_clause = self.formula.analyze_top_level_conflict(&self.state, conflict);
+                    self.state.record_addition(empty_clause);
+                    return Err(Vec::new());
+                }
+
+                let (learned, backjump_level) = self.formula.analyze_conflict(&mut self.state, conflict);
+                let asserting_literal = learned[0];
+                let lbd = learned
+                    .iter()
+                    .map(|&literal| self.state.level_of(literal))
+                    .collect::<hashbrown::HashSet<_>>()
+                    .len();
+                self.state.record_addition(learned.clone());
+
+                self.state.backjump(backjump_level);
+
+                let learned_index = self.formula.add_learned_clause(learned, lbd);
+                self.state.assign(asserting_literal, Some(learned_index));
+
+                self.conflicts += 1;
+                if self.conflicts >= self.next_reduction {
+                    self.formula.reduce_learned_clauses(&mut self.state);
+                    self.next_reduction += self.next_reduction / 2;
+                }
+
+                if self.vivify_enabled && self.conflicts >= self.next_vivification && self.state.level == 0 {
+                    self.formula.vivify_clauses(&mut self.state);
+                    self.next_vivification += self.next_vivification / 2;
+                }
+
+                self.record_conflict_for_restart(lbd);
+
+                continue;
+            }
+
+            if let Some(decision) = self.pending_assumption(assumptions, &assumption_vars) {
+                let literal = decision?;
+                self.state.node_counter += 1;
+                self.state.level += 1;
+                self.state.assign(literal, None);
+                continue;
+            }
+
+            if self.formula.is_satisfied(&self.state) {
+                return Ok(self.state.trail.iter().map(|entry| entry.literal).collect());
+            }
+
+            self.state.node_counter += 1;
+            self.state.level += 1;
+            let literal = self.state.pick_branch_literal(self.formula.nvars);
+            self.state.assign(literal, None);
+        }
+    }
+}
+
+/// Iterativně prohledává strom možných [přiřazení](`Assignment`) metodou CDCL
+/// (*conflict-driven clause learning*) nad klauzulemi se sledovanými literály. Jednorázová
+/// obálka nad [`Solver::solve_under_assumptions`] bez předpokladů — pro opakované dotazy nad
+/// stejnou formulí použijte přímo [`Solver`].
+///
+/// Postupuje takto:
+///
+/// 1. Ořeže strom k prozkoumání [unit propagací](`Formula::unit_propagate`).
+///
+/// 2. Pokud propagace narazila na konflikt a jsme už na rozhodovací úrovni `0`, problém je
+///    nesplnitelný (`None`). Jinak z konfliktu metodou prvního UIP [odvodí novou
+///    klauzuli](`Formula::analyze_conflict`), [zaznamená ji do DRAT důkazu](`State::record_addition`)
+///    (je-li zapnutý), [přidá ji mezi klauzule formule](`Formula::add_learned_clause`), couvne na
+///    spočítanou úroveň a jediný zbylý literál naučené klauzule jednotkově přiřadí.
+///
+/// 3. Pokud je formule [splněná](`Formula::is_satisfied`), vrátí seznam literálů s pravdivým
+///    vyhodnocením.
+///
+/// 4. Jinak otevře novou rozhodovací úroveň a pomocí aktivitní heuristiky
+///    [`State::pick_branch_literal`] vybere literál příštího rozhodnutí.
+///
+pub fn solve(state: &mut State, formula: Formula) -> SatResult {
+    let mut solver = Solver::new(formula);
+    solver.state = std::mem::take(state);
+    let result = solver.solve_under_assumptions(&[]);
+    *state = solver.state;
+    result.ok()
 }
 
 /// Převede text [DIMACS formátu](https://web.archive.org/web/20190325181937/https://www.satcompetition.org/2009/format-benchmarks2009.html)
-/// do datové struktury [`Formula`].
+/// do datové struktury [`Formula`], včetně počtu proměnných z hlavičky `p cnf <nvars> <nclauses>`.
 ///
 pub fn parse_dimacs_file(problem: &str) -> Result<Formula> {
-    let clauses = problem
-        .lines()
-        .skip_while(|line| line.starts_with('c'))
-        .skip(1)
-        .into_iter()
+    let mut lines = problem.lines().skip_while(|line| line.starts_with('c'));
+    let header = lines.next().context("Missing DIMACS header line")?;
+    let nvars = header
+        .split_whitespace()
+        .nth(2)
+        .context("Malformed DIMACS header")?
+        .parse()
+        .context("Malformed DIMACS header")?;
+
+    let clauses = lines
         .map(|line| {
             line.split_whitespace()
                 .take_while(|str| str != &"0")
@@ -218,18 +1286,175 @@ pub fn parse_dimacs_file(problem: &str) -> Result<Formula> {
         .collect::<Result<Vec<_>, _>>()
         .context("Failed to parse dimacs file")?;
 
-    Ok(Formula {
-        clauses,
+    let mut formula = Formula {
+        nvars,
         ..Default::default()
-    })
+    };
+    for clause in clauses {
+        formula.add_clause(clause);
+    }
+
+    Ok(formula)
+}
+
+/// Projde argumenty příkazové řádky a vrátí jediný poziční argument (cestu k souboru
+/// s problémem), hodnotu volby `--proof <soubor>`, hodnotu volby `--restart <strategie>`
+/// a `true`, pokud byla přítomná přepínací volba `--vivify`.
+///
+fn parse_cli_args() -> (Option<String>, Option<String>, Option<String>, bool) {
+    let mut positional = None;
+    let mut proof_path = None;
+    let mut restart_strategy = None;
+    let mut vivify = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--proof" {
+            proof_path = args.next();
+        } else if arg == "--restart" {
+            restart_strategy = args.next();
+        } else if arg == "--vivify" {
+            vivify = true;
+        } else {
+            positional = Some(arg);
+        }
+    }
+
+    (positional, proof_path, restart_strategy, vivify)
 }
 
 /// Načte soubour předaný při spuštění a pokusí se ho načíst pomocí funkce [`parse_dimacs_file`].
 ///
 pub fn process_args() -> Result<Formula> {
-    env::args()
-        .nth(1)
+    parse_cli_args()
+        .0
         .context("No path given")
         .and_then(|path| fs::read_to_string(path).context("failed to read file"))
         .and_then(|problem| parse_dimacs_file(&problem).context("failed to parse problem file"))
 }
+
+/// Vrátí cestu ze volby `--proof <soubor>`, pokud byla mezi argumenty příkazové řádky zadaná.
+/// Je-li `Some`, má se spolu s ní zapnout [`State::enable_proof_logging`] a po doběhnutí
+/// [`solve`] nesplnitelného problému zavolat [`write_drat_proof`].
+///
+pub fn process_proof_arg() -> Option<String> {
+    parse_cli_args().1
+}
+
+/// Přečte volbu `--restart <luby|dynamic>` z argumentů příkazové řádky a vrátí odpovídající
+/// [`RestartStrategy`] pro [`Solver::set_restart_strategy`] — jiná hodnota nebo chybějící volba
+/// restarty nechá vypnuté (`None`).
+///
+pub fn process_restart_arg() -> Option<RestartStrategy> {
+    match parse_cli_args().2.as_deref() {
+        Some("luby") => Some(RestartStrategy::Luby),
+        Some("dynamic") => Some(RestartStrategy::Dynamic),
+        _ => None,
+    }
+}
+
+/// `true`, pokud byla mezi argumenty příkazové řádky zadaná přepínací volba `--vivify` — má
+/// se spolu s ní zapnout [`Solver::enable_vivification`].
+///
+pub fn process_vivify_arg() -> bool {
+    parse_cli_args().3
+}
+
+/// Zapíše nasbírané [kroky DRAT důkazu](`ProofStep`) (viz [`State::enable_proof_logging`]) do
+/// souboru: přidání klauzule jako řádek literálů zakončený `0`, smazání stejně, ale s
+/// prefixem `d`. Díky tomu lze výsledek UNSAT nezávisle ověřit externím
+/// [DRAT-checkerem](https://www.cs.utexas.edu/~marijn/drat-trim/). Pokud zápis důkazu
+/// nebyl zapnutý, nic nedělá.
+///
+pub fn write_drat_proof(state: &State, path: impl AsRef<std::path::Path>) -> Result<()> {
+    use std::io::Write;
+
+    let Some(steps) = &state.proof else {
+        return Ok(());
+    };
+
+    let mut writer = std::io::BufWriter::new(fs::File::create(path).context("Failed to create proof file")?);
+    for step in steps {
+        let (prefix, clause) = match step {
+            ProofStep::Addition(clause) => ("", clause),
+            ProofStep::Deletion(clause) => ("d ", clause),
+        };
+
+        write!(writer, "{prefix}").context("Failed to write proof file")?;
+        for literal in clause {
+            write!(writer, "{literal} ").context("Failed to write proof file")?;
+        }
+        writeln!(writer, "0").context("Failed to write proof file")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Vrátí `true`, pokud `assignment` splňuje formuli zadanou jako DIMACS text.
+    fn satisfies(problem: &str, assignment: &[Assignment]) -> bool {
+        let formula = parse_dimacs_file(problem).expect("invalid fixture");
+        let values: hashbrown::HashSet<Assignment> = assignment.iter().copied().collect();
+        (0..formula.clauses.len()).all(|i| {
+            formula.clauses[i]
+                .iter()
+                .any(|&literal| values.contains(&literal))
+        })
+    }
+
+    #[test]
+    fn solves_trivially_satisfiable_formula() {
+        let problem = "p cnf 2 2\n1 2 0\n-1 2 0\n";
+        let formula = parse_dimacs_file(problem).unwrap();
+        let mut state = State::default();
+        let assignment = solve(&mut state, formula).expect("expected SAT");
+        assert!(satisfies(problem, &assignment));
+    }
+
+    #[test]
+    fn detects_unsat_via_conflict_driven_learning() {
+        let problem = "p cnf 1 2\n1 0\n-1 0\n";
+        let formula = parse_dimacs_file(problem).unwrap();
+        let mut state = State::default();
+        assert_eq!(solve(&mut state, formula), None);
+    }
+
+    #[test]
+    fn empty_input_clause_is_unsat_without_making_any_decision() {
+        let formula = parse_dimacs_file("p cnf 2 2\n0\n1 2 0\n").unwrap();
+        assert!(formula.has_empty_clause);
+
+        let mut solver = Solver::new(formula);
+        assert_eq!(solver.solve_under_assumptions(&[]), Err(Vec::new()));
+        assert_eq!(solver.state.node_counter, 0);
+    }
+
+    #[test]
+    fn drat_proof_terminates_with_the_empty_clause_on_unsat() {
+        let formula = parse_dimacs_file("p cnf 1 2\n1 0\n-1 0\n").unwrap();
+        let mut state = State::default();
+        state.enable_proof_logging();
+
+        assert_eq!(solve(&mut state, formula), None);
+
+        let last_step = state.proof.as_ref().and_then(|steps| steps.last()).expect("expected a recorded proof step");
+        match last_step {
+            ProofStep::Addition(clause) => assert!(clause.is_empty()),
+            ProofStep::Deletion(_) => panic!("proof should terminate with an added empty clause, not a deletion"),
+        }
+    }
+
+    #[test]
+    fn failed_assumption_is_reported_in_the_returned_core() {
+        let formula = parse_dimacs_file("p cnf 2 1\n1 2 0\n").unwrap();
+        let mut solver = Solver::new(formula);
+
+        let result = solver.solve_under_assumptions(&[-1, -2]);
+        assert!(result.is_err());
+        let core = result.unwrap_err();
+        assert!(core.contains(&-1) && core.contains(&-2));
+    }
+}
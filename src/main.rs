@@ -1,32 +1,64 @@
 use anyhow::{Context, Result};
 use cpu_time::ProcessTime;
-use solver::{process_args, solve, State};
+use solver::{process_args, process_proof_arg, process_restart_arg, process_vivify_arg, write_drat_proof, Solver};
 
 fn main() -> Result<()> {
     let start = ProcessTime::try_now().context("Getting process time failed")?;
     let formula = process_args()?;
-    let mut state = State::default();
+    let proof_path = process_proof_arg();
+    let restart_strategy = process_restart_arg();
+    let vivify = process_vivify_arg();
+
+    let mut solver = Solver::new(formula);
+    if proof_path.is_some() {
+        solver.enable_proof_logging();
+    }
+    if let Some(strategy) = restart_strategy {
+        solver.set_restart_strategy(strategy);
+    }
+    if vivify {
+        solver.enable_vivification();
+    }
     let time_init = start.try_elapsed().context("Getting process time failed")?;
 
     let start = ProcessTime::try_now().context("Getting process time failed")?;
-    match solve(&mut state, formula) {
-        Some(mut assignments) => {
+    match solver.solve_under_assumptions(&[]) {
+        Ok(mut assignments) => {
             assignments.sort();
             println!("SAT");
             println!("true: {:?}", assignments);
         }
-        None => println!("UNSAT\n"),
+        Err(_) => {
+            println!("UNSAT\n");
+            if let Some(path) = &proof_path {
+                write_drat_proof(solver.state(), path)?;
+            }
+        }
     }
     let time_solution = start.try_elapsed().context("Getting process time failed")?;
 
+    let state = solver.state();
     println!(
         "
-setup time:        {:#?}
-solve time:        {:#?}
-unit propagations: {}   
-nodes visited:     {}   
+setup time:          {:#?}
+solve time:          {:#?}
+unit propagations:   {}
+nodes visited:       {}
+learned clauses kept:    {}
+learned clauses deleted: {}
+restarts:                {}
+vivified literals removed: {}
+vivified clauses eliminated: {}
 ",
-        time_init, time_solution, state.unit_propagation_counter, state.node_counter
+        time_init,
+        time_solution,
+        state.unit_propagation_counter,
+        state.node_counter,
+        state.learned_clauses_kept,
+        state.learned_clauses_deleted,
+        state.restart_counter,
+        state.vivified_literals_removed,
+        state.vivified_clauses_eliminated
     );
     return Ok(());
 }